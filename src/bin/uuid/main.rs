@@ -1,7 +1,14 @@
 use mac_address::{get_mac_address, mac_address_by_name, MacAddress};
-use std::{convert::TryInto, fmt, process::exit, str::FromStr, time::SystemTime};
+use std::{
+    convert::TryInto,
+    fmt,
+    process::exit,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use structopt::StructOpt;
-use uuid::Uuid;
+use time::{Duration, OffsetDateTime};
+use uuid::{Builder, Uuid, Variant};
 
 #[derive(StructOpt, Debug, Clone)]
 /// A simple command-line tool for generating and inspecting UUIDs
@@ -37,14 +44,26 @@ struct Opt {
     ///
     /// - `5`: A SHA1-hashed namespace + name uuid
     ///
+    /// - `6`: A reordered, sortable datetime + MAC address uuid
+    ///
+    /// - `7`: A Unix-time, sortable uuid
+    ///
     /// Supported versions for generating:
     ///
     /// - `0`: A nil (all-zeros) uuid
     ///
     /// - `1`: A datetime + MAC address uuid
     ///
+    /// - `3`: An MD5-hashed `--namespace` + `--name` uuid
+    ///
     /// - `4`: A random uuid
     ///
+    /// - `5`: A SHA1-hashed `--namespace` + `--name` uuid
+    ///
+    /// - `6`: A reordered, sortable datetime + MAC address uuid
+    ///
+    /// - `7`: A Unix-time, sortable uuid
+    ///
     #[structopt(short = "v", long = "version")]
     version_mode: Option<VersionMode>,
     /// The output format to use when writing the generated or decoded uuid to
@@ -64,6 +83,11 @@ struct Opt {
     /// - `ms`: hyphenated form surrounded by braces, as used by microsoft
     ///     (e.g `{5c16fcb1-76ba-4b06-8fdf-34a6aeb478c5}`)
     ///
+    /// - `raw`: the 16 uuid bytes, written straight to stdout
+    ///
+    /// - `base64`: standard Base64 of the 16 uuid bytes
+    ///     (e.g `XBb8sXa6SwaP3zSmrrR4xQ`)
+    ///
     #[structopt(short = "o", long)]
     output_format: Option<Format>,
 
@@ -92,11 +116,58 @@ struct Opt {
     /// If omitted, the mac address of a non-local-loopback interface is used
     #[structopt(long)]
     mac_interface: Option<String>,
+
+    /// Namespace uuid to hash `--name` against when generating a v3 or v5
+    /// name-based uuid
+    ///
+    /// Accepts one of the well-known namespace names `dns`, `url`, `oid`, or
+    /// `x500`, or any uuid in the formats accepted by the `<uuid>` argument
+    #[structopt(long, parse(try_from_str = parse_namespace))]
+    namespace: Option<Uuid>,
+
+    /// Name to hash with `--namespace` when generating a v3 or v5 name-based
+    /// uuid
+    #[structopt(long)]
+    name: Option<String>,
+
+    /// Treat the uuid's first three fields as little-endian, as used by
+    /// Microsoft GUIDs extracted from memory (e.g. a registry value or a COM
+    /// structure)
+    ///
+    /// On input, the byte swap is reversed before the uuid is otherwise
+    /// processed. On output, the same swap is applied to the uuid before
+    /// formatting it
+    #[structopt(long)]
+    guid_le: bool,
+
+    /// Number of uuids to generate
+    ///
+    /// Ignored when inspecting an existing `<uuid>` argument
+    #[structopt(short = "n", long, default_value = "1")]
+    count: usize,
 }
 
 /// Parse a uuid in formats supported by the uuid crate, allowing for surrounding
-/// braces for ms format
+/// braces for ms format, a 22-char Base64 string, or `-` to read 16 raw bytes
+/// from stdin
 fn parse_uuid(s: &str) -> Result<Uuid, uuid::Error> {
+    if s == "-" {
+        let mut bytes = [0u8; 16];
+        std::io::Read::read_exact(&mut std::io::stdin(), &mut bytes)
+            .expect("Could not read uuid bytes from stdin");
+        return Ok(Uuid::from_bytes(bytes));
+    }
+
+    if s.len() == 22 {
+        if let Some(bytes) = base64::decode_config(s, base64::STANDARD_NO_PAD)
+            .or_else(|_| base64::decode_config(s, base64::URL_SAFE_NO_PAD))
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+        {
+            return Ok(Uuid::from_bytes(bytes));
+        }
+    }
+
     let s = if (34..=38).contains(&s.len()) && s.starts_with('{') && s.ends_with('}') {
         &s[1..(s.len() - 1)]
     } else {
@@ -105,12 +176,26 @@ fn parse_uuid(s: &str) -> Result<Uuid, uuid::Error> {
     s.parse()
 }
 
+/// Parse a `--namespace` argument, accepting either a well-known namespace
+/// name or any uuid accepted by `parse_uuid`
+fn parse_namespace(s: &str) -> Result<Uuid, uuid::Error> {
+    Ok(match s {
+        "dns" => Uuid::NAMESPACE_DNS,
+        "url" => Uuid::NAMESPACE_URL,
+        "oid" => Uuid::NAMESPACE_OID,
+        "x500" => Uuid::NAMESPACE_X500,
+        _ => parse_uuid(s)?,
+    })
+}
+
 #[derive(Debug, Clone)]
 pub enum Format {
     Simple,
     Hyphenated,
     Urn,
     Microsoft,
+    Raw,
+    Base64,
 }
 
 impl FromStr for Format {
@@ -121,43 +206,58 @@ impl FromStr for Format {
             "hyphenated" => Format::Hyphenated,
             "urn" => Format::Urn,
             "ms" | "microsoft" => Format::Microsoft,
+            "raw" => Format::Raw,
+            "base64" => Format::Base64,
             _ => return Err("invalid format name"),
         })
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct VersionMode(uuid::Version);
+/// The version of uuid being generated or inspected
+///
+/// This wraps a raw version number rather than `uuid::Version`, since that
+/// type has no variant for versions (such as `7`) that postdate the `uuid`
+/// crate we depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionMode(u8);
 
 impl FromStr for VersionMode {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(VersionMode(
-            match s.parse::<u8>().map_err(|e| e.to_string())? {
-                0 => uuid::Version::Nil,
-                1 => uuid::Version::Mac,
-                2 => uuid::Version::Dce,
-                3 => uuid::Version::Md5,
-                4 => uuid::Version::Random,
-                5 => uuid::Version::Sha1,
-                _ => return Err("Version out of range".into()),
-            },
-        ))
+        let version = s.parse::<u8>().map_err(|e| e.to_string())?;
+        match version {
+            0..=7 => Ok(VersionMode(version)),
+            _ => Err("Version out of range".into()),
+        }
     }
 }
 
-pub struct VersionDesc(Option<uuid::Version>);
+/// The version nibble (bits 4..8 of byte 6) of a uuid, read directly from its
+/// bytes so that versions `uuid::Uuid::get_version` doesn't recognise (such
+/// as `7`) can still be inspected
+fn version_nibble(uuid: &Uuid) -> u8 {
+    uuid.as_bytes()[6] >> 4
+}
+
+enum VersionDesc {
+    Known(uuid::Version),
+    SortMac,
+    SortRand,
+    Unknown,
+}
 
 impl fmt::Display for VersionDesc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(match self.0 {
-            Some(uuid::Version::Nil) => "Nil",
-            Some(uuid::Version::Mac) => "v1 MAC Address",
-            Some(uuid::Version::Dce) => "v2 DCE",
-            Some(uuid::Version::Md5) => "v3 MD5",
-            Some(uuid::Version::Random) => "v4 Random",
-            Some(uuid::Version::Sha1) => "v5 SHA-1",
-            None => "Unknown",
+        f.write_str(match self {
+            VersionDesc::Known(uuid::Version::Nil) => "Nil",
+            VersionDesc::Known(uuid::Version::Mac) => "v1 MAC Address",
+            VersionDesc::Known(uuid::Version::Dce) => "v2 DCE",
+            VersionDesc::Known(uuid::Version::Md5) => "v3 MD5",
+            VersionDesc::Known(uuid::Version::Random) => "v4 Random",
+            VersionDesc::Known(uuid::Version::Sha1) => "v5 SHA-1",
+            VersionDesc::SortMac => "v6 Sortable MAC Address",
+            VersionDesc::SortRand => "v7 Unix Time, Sortable",
+            VersionDesc::Unknown => "Unknown",
         })
     }
 }
@@ -186,66 +286,181 @@ fn ticks_from_timestamp(time: SystemTime) -> u64 {
     seconds * 10000000 + subsec_nanos / 100
 }
 
-fn main() {
-    let options = Opt::from_args();
+/// Reinterpret a uuid's first three fields between RFC4122 byte order and
+/// the little-endian order Microsoft GUIDs store them in. Applying this
+/// twice returns the original uuid
+fn swap_guid_endianness(uuid: Uuid) -> Uuid {
+    let (d1, d2, d3, d4) = uuid.to_fields_le();
+    Uuid::from_fields(d1, d2, d3, d4).expect("uuid field bytes are always valid")
+}
 
-    use std::io::Write;
-    let stderr = std::io::stderr();
-    let mut stderr = stderr.lock();
+/// Ticks (100ns intervals) between the RFC4122 Gregorian epoch
+/// (1582-10-15T00:00:00Z) and the unix epoch
+const TICKS_UNIX_EPOCH_OFFSET: i64 = 122_192_928_000_000_000;
 
-    let uuid = if let Some(uuid) = options.uuid {
-        if let (Some(VersionMode(expected_version)), Some(version)) =
-            (options.version_mode, uuid.get_version())
-        {
-            if expected_version != version {
-                eprintln!("error: Provided <uuid> did not match `--version-mode`");
-                exit(1)
-            }
+/// Format an RFC4122 tick timestamp as an ISO-8601 UTC datetime
+///
+/// Ticks before the unix epoch (or beyond the range of `OffsetDateTime`) are
+/// instead reported as the signed tick offset from 1970-01-01T00:00:00Z
+fn format_timestamp(ticks: u64) -> String {
+    let unix_ticks = ticks as i64 - TICKS_UNIX_EPOCH_OFFSET;
+    if unix_ticks < 0 {
+        return format!("{} ticks from the unix epoch", unix_ticks);
+    }
+    let seconds = unix_ticks.div_euclid(10_000_000);
+    let subsec_100ns = unix_ticks.rem_euclid(10_000_000);
+
+    match OffsetDateTime::from_unix_timestamp(seconds) {
+        Ok(datetime) => {
+            let datetime = datetime + Duration::nanoseconds(subsec_100ns * 100);
+            format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+                datetime.year(),
+                datetime.month() as u8,
+                datetime.day(),
+                datetime.hour(),
+                datetime.minute(),
+                datetime.second(),
+                datetime.millisecond()
+            )
         }
-        uuid
-    } else {
-        match options.version_mode {
-            Some(VersionMode(uuid::Version::Nil)) => Uuid::nil(),
-            Some(VersionMode(uuid::Version::Mac)) => {
-                let ticks = options.timestamp_ticks.unwrap_or_else(|| {
-                    let now = SystemTime::now();
-                    ticks_from_timestamp(now)
-                });
-
-                let counter = options.counter.unwrap_or(rand::random());
-
-                let ts = uuid::v1::Timestamp::from_rfc4122(ticks, counter);
-
-                let node_id =
-                    options
-                        .mac_address
-                        .or_else(|| {
-                            options.mac_interface.as_deref().map(|interface| {
-                                match mac_address_by_name(interface).expect(&format!(
-                                    "Could not get mac address for interface {}",
-                                    interface
-                                )) {
-                                    Some(mac_address) => mac_address,
-                                    None => {
-                                        eprintln!("error: MAC address could not be obtained for `--mac-interface={}`", interface);
-                                        exit(1)},
-                                }
-                            })
-                        })
-                        .or_else(|| get_mac_address().expect("Could not get mac address"))
-                        .expect("No mac address found");
-
-                Uuid::new_v1(ts, &node_id.bytes()).expect("could not build uuid")
-            }
-            Some(VersionMode(uuid::Version::Random)) | None => Uuid::new_v4(),
-            _ => {
-                println!("error: Only 0, 1, and 4 are supported for '--version <version-mode>' without a provided <uuid>");
+        Err(_) => format!("{} ticks from the unix epoch", unix_ticks),
+    }
+}
+
+/// Gather the timestamp, clock sequence counter, and node id used to build a
+/// version 1 or version 6 uuid, from the `--timestamp-ticks`, `--counter`,
+/// `--mac-address`, and `--mac-interface` options
+fn v1_fields(options: &Opt) -> (u64, u16, [u8; 6]) {
+    let ticks = options.timestamp_ticks.unwrap_or_else(|| {
+        let now = SystemTime::now();
+        ticks_from_timestamp(now)
+    });
+
+    let counter = options.counter.unwrap_or(rand::random());
+
+    let node_id = options
+        .mac_address
+        .or_else(|| {
+            options.mac_interface.as_deref().map(|interface| {
+                match mac_address_by_name(interface).expect(&format!(
+                    "Could not get mac address for interface {}",
+                    interface
+                )) {
+                    Some(mac_address) => mac_address,
+                    None => {
+                        eprintln!(
+                            "error: MAC address could not be obtained for `--mac-interface={}`",
+                            interface
+                        );
+                        exit(1)
+                    }
+                }
+            })
+        })
+        .or_else(|| get_mac_address().expect("Could not get mac address"))
+        .expect("No mac address found");
+
+    (ticks, counter, node_id.bytes())
+}
+
+/// Generate a new version 6 (reordered, sortable) uuid
+///
+/// Reuses the same timestamp/counter/node id inputs as version 1, but places
+/// the high bits of the timestamp first so that a lexicographic comparison
+/// of two v6 uuids sorts the same as a numeric comparison of their
+/// timestamps
+fn generate_v6(options: &Opt) -> Uuid {
+    let (ticks, counter, node_id) = v1_fields(options);
+
+    let time_high = ((ticks >> 28) & 0xFFFF_FFFF) as u32;
+    let time_mid = ((ticks >> 12) & 0xFFFF) as u16;
+    let time_low = (ticks & 0xFFF) as u16;
+
+    let mut bytes = [0u8; 16];
+    bytes[0..4].copy_from_slice(&time_high.to_be_bytes());
+    bytes[4..6].copy_from_slice(&time_mid.to_be_bytes());
+    bytes[6..8].copy_from_slice(&time_low.to_be_bytes());
+    bytes[6] = (bytes[6] & 0x0F) | 0x60;
+    bytes[8..10].copy_from_slice(&counter.to_be_bytes());
+    bytes[10..16].copy_from_slice(&node_id);
+
+    Builder::from_bytes(bytes)
+        .set_variant(Variant::RFC4122)
+        .build()
+}
+
+/// Generate a new version 7 (Unix-time, sortable) uuid
+///
+/// The 48 most-significant bits hold a big-endian unix millisecond
+/// timestamp, immediately followed by the 4 version bits; the variant bits
+/// sit at the top of byte 8, and the remaining bits are filled with random
+/// data
+fn generate_v7() -> Uuid {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Could not get duration since unix epoch")
+        .as_millis() as u64;
+
+    let mut bytes = [0u8; 16];
+    bytes[0..6].copy_from_slice(&millis.to_be_bytes()[2..8]);
+    bytes[6..16].copy_from_slice(&rand::random::<[u8; 10]>());
+    bytes[6] = (bytes[6] & 0x0F) | 0x70;
+
+    Builder::from_bytes(bytes)
+        .set_variant(Variant::RFC4122)
+        .build()
+}
+
+/// Generate a new uuid according to `options.version_mode` (defaulting to a
+/// random v4 uuid)
+fn generate(options: &Opt) -> Uuid {
+    match options.version_mode.map(|VersionMode(v)| v) {
+        Some(0) => Uuid::nil(),
+        Some(1) => {
+            let (ticks, counter, node_id) = v1_fields(options);
+            let ts = uuid::v1::Timestamp::from_rfc4122(ticks, counter);
+            Uuid::new_v1(ts, &node_id).expect("could not build uuid")
+        }
+        Some(v) if v == 3 || v == 5 => {
+            let namespace = options.namespace.unwrap_or_else(|| {
+                eprintln!("error: `--namespace` is required when generating a v3 or v5 uuid");
                 exit(1);
+            });
+            let name = options.name.as_deref().unwrap_or_else(|| {
+                eprintln!("error: `--name` is required when generating a v3 or v5 uuid");
+                exit(1);
+            });
+
+            if v == 3 {
+                Uuid::new_v3(&namespace, name.as_bytes())
+            } else {
+                Uuid::new_v5(&namespace, name.as_bytes())
             }
         }
+        Some(4) | None => Uuid::new_v4(),
+        Some(6) => generate_v6(options),
+        Some(7) => generate_v7(),
+        _ => {
+            println!("error: Only 0, 1, 3, 4, 5, 6, and 7 are supported for '--version <version-mode>' without a provided <uuid>");
+            exit(1);
+        }
+    }
+}
+
+/// Write version/variant and any version-specific diagnostics for `uuid` to
+/// stderr
+fn print_diagnostics(uuid: Uuid, stderr: &mut impl std::io::Write) {
+    use std::io::Write;
+
+    let version_desc = match uuid.get_version() {
+        Some(v) => VersionDesc::Known(v),
+        None if version_nibble(&uuid) == 6 => VersionDesc::SortMac,
+        None if version_nibble(&uuid) == 7 => VersionDesc::SortRand,
+        None => VersionDesc::Unknown,
     };
 
-    write!(stderr, "{}", VersionDesc(uuid.get_version())).expect("Could not write to stderr");
+    write!(stderr, "{}", version_desc).expect("Could not write to stderr");
 
     write!(stderr, " {}", VariantDesc(uuid.get_variant())).expect("Could not write to stderr");
 
@@ -262,20 +477,97 @@ fn main() {
             let mac = MacAddress::new(node_id.try_into().unwrap());
             writeln!(
                 stderr,
-                "timestamp_ticks={} counter={} node_id={}",
-                timestamp_ticks, counter, mac
+                "timestamp_ticks={} counter={} node_id={} datetime={}",
+                timestamp_ticks,
+                counter,
+                mac,
+                format_timestamp(timestamp_ticks)
             )
             .expect("Could not write to stderr");
         }
+        None if version_nibble(&uuid) == 6 => {
+            let bytes = uuid.as_bytes();
+            let time_high = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as u64;
+            let time_mid = u16::from_be_bytes(bytes[4..6].try_into().unwrap()) as u64;
+            let time_low = u16::from_be_bytes([bytes[6] & 0x0F, bytes[7]]) as u64;
+            let timestamp_ticks = (time_high << 28) | (time_mid << 12) | time_low;
+            let counter = u16::from_be_bytes([bytes[8] & 0x3F, bytes[9]]);
+            let node_id = MacAddress::new(bytes[10..16].try_into().unwrap());
+            writeln!(
+                stderr,
+                "timestamp_ticks={} counter={} node_id={} datetime={}",
+                timestamp_ticks,
+                counter,
+                node_id,
+                format_timestamp(timestamp_ticks)
+            )
+            .expect("Could not write to stderr");
+        }
+        None if version_nibble(&uuid) == 7 => {
+            let mut ts_bytes = [0u8; 8];
+            ts_bytes[2..8].copy_from_slice(&uuid.as_bytes()[0..6]);
+            let unix_ms = u64::from_be_bytes(ts_bytes);
+            writeln!(stderr, "unix_ms={}", unix_ms).expect("Could not write to stderr");
+        }
         _ => (),
     }
+}
+
+/// Write `uuid` to stdout in `options.output_format`, applying
+/// `options.guid_le` first if set
+fn print_uuid(uuid: Uuid, options: &Opt) {
+    use std::io::Write;
+
+    let uuid = if options.guid_le {
+        swap_guid_endianness(uuid)
+    } else {
+        uuid
+    };
 
     match options.output_format {
         Some(Format::Simple) => println!("{}", uuid::adapter::Simple::from_uuid(uuid)),
         Some(Format::Urn) => println!("{}", uuid::adapter::Urn::from_uuid(uuid)),
         Some(Format::Microsoft) => println!("{{{}}}", uuid::adapter::Hyphenated::from_uuid(uuid)),
+        Some(Format::Raw) => std::io::stdout()
+            .write_all(uuid.as_bytes())
+            .expect("Could not write to stdout"),
+        Some(Format::Base64) => {
+            println!(
+                "{}",
+                base64::encode_config(uuid.as_bytes(), base64::STANDARD_NO_PAD)
+            )
+        }
         Some(Format::Hyphenated) | None => {
             println!("{}", uuid::adapter::Hyphenated::from_uuid(uuid))
         }
     }
 }
+
+fn main() {
+    let mut options = Opt::from_args();
+    if options.guid_le {
+        options.uuid = options.uuid.map(swap_guid_endianness);
+    }
+
+    let stderr = std::io::stderr();
+    let mut stderr = stderr.lock();
+
+    if let Some(uuid) = options.uuid {
+        if let Some(VersionMode(expected_version)) = options.version_mode {
+            if expected_version != version_nibble(&uuid) {
+                eprintln!("error: Provided <uuid> did not match `--version-mode`");
+                exit(1)
+            }
+        }
+        print_diagnostics(uuid, &mut stderr);
+        print_uuid(uuid, &options);
+    } else {
+        for i in 0..options.count {
+            let uuid = generate(&options);
+            if i == 0 {
+                print_diagnostics(uuid, &mut stderr);
+            }
+            print_uuid(uuid, &options);
+        }
+    }
+}